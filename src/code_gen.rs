@@ -4,33 +4,196 @@
  * React component that is mounted on the page.
 */
 
+/// Controls how the server branch of [`build_entrypoint`] renders the
+/// mounted tree. `String` buffers the full document via `renderToString`
+/// before returning it to the Rust host; `Stream` renders progressively via
+/// [`RenderTarget`]'s `renderToReadableStream`/`renderToPipeableStream` so
+/// the host can flush the shell as soon as it's ready.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RenderMode {
+    String,
+    Stream,
+}
+
+/// Which runtime `RenderMode::Stream` emits a streaming renderer for.
+/// `Edge` uses `renderToReadableStream` and resolves `Index` to
+/// `{ stream, status }` carrying a Web `ReadableStream`; `Node` uses
+/// `renderToPipeableStream` and resolves `Index` to `{ pipe, abort, status }`
+/// with a Node-style `pipe`/`abort` pair, matching React's own
+/// documented `onShellReady` pattern. Ignored when `render_mode` is
+/// `RenderMode::String`, since `renderToString` doesn't differ by target.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RenderTarget {
+    Edge,
+    Node,
+}
+
+/// Controls how client-side layout imports are emitted. `Eager` keeps the
+/// existing static `import Layout from '...'` so every layout in the path
+/// group ships in the initial bundle; `Lazy` emits `React.lazy(() =>
+/// import('...'))` so the bundler can split each layout into its own chunk.
+/// Only meaningful for the client branch — `renderToString`/streaming on
+/// the server always needs the layouts eagerly, so the server branch
+/// ignores this and imports eagerly regardless.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LayoutImportStrategy {
+    Eager,
+    Lazy,
+}
+
+/// The modules that make up a single nested route segment. `layout` is
+/// required; `error`, `loading` and `not_found` mirror the Next.js-style
+/// special files and are wrapped around the layout when present.
+#[derive(Debug, Clone)]
+pub struct SegmentModules {
+    pub layout: String,
+    pub error: Option<String>,
+    pub loading: Option<String>,
+    pub not_found: Option<String>,
+    /// Whether this segment's layout is a client component (`"use client"`)
+    /// as opposed to a server-only component. Only consulted by
+    /// [`build_rsc_entrypoints`]; [`build_entrypoint`] treats every segment
+    /// as isomorphic.
+    pub is_client_component: bool,
+    /// Optional `head` module for this segment. Its default export is a
+    /// plain function (no hooks, not a component — in the spirit of
+    /// Next.js's `generateMetadata`) returning the segment's title/meta/link
+    /// tags. Only called by the server branch of [`build_entrypoint`];
+    /// deeper segments override shallower ones on tag key when the merged
+    /// head is assembled.
+    pub head: Option<String>,
+}
+
+impl SegmentModules {
+    pub fn new(layout: impl Into<String>) -> Self {
+        Self {
+            layout: layout.into(),
+            error: None,
+            loading: None,
+            not_found: None,
+            is_client_component: false,
+            head: None,
+        }
+    }
+}
+
+/// One entry in the client-reference manifest produced by
+/// [`build_rsc_entrypoints`]: maps a client segment's layout path to the
+/// chunk and export the client runtime fetches to hydrate that segment's
+/// island.
+#[derive(Debug, Clone)]
+pub struct ClientReference {
+    pub segment_path: String,
+    pub chunk: String,
+    pub export_name: String,
+}
+
 pub fn build_entrypoint(
-    path_group: &[String],
+    path_group: &[SegmentModules],
     is_server: bool,
     live_reload_import: &str,
+    render_mode: RenderMode,
+    render_target: RenderTarget,
+    layout_import_strategy: LayoutImportStrategy,
 ) -> String {
+    // Lazy layout imports only make sense once React can suspend while
+    // fetching the chunk, which `renderToString`/streaming SSR can't do.
+    let lazy_layouts = !is_server && layout_import_strategy == LayoutImportStrategy::Lazy;
+
     // Generate the synthetic entrypoint content
     let mut entrypoint_content = String::from("import React from 'react';\n");
     entrypoint_content += &format!("import mountLiveReload from '{}';\n\n", live_reload_import);
 
-    for (j, path) in path_group.iter().enumerate() {
-        entrypoint_content += &format!("import Layout{} from '{}';\n", j, path);
+    for (j, segment) in path_group.iter().enumerate() {
+        if lazy_layouts {
+            entrypoint_content += &format!(
+                "const Layout{} = React.lazy(() => import('{}'));\n",
+                j, segment.layout
+            );
+        } else {
+            entrypoint_content += &format!("import Layout{} from '{}';\n", j, segment.layout);
+        }
+        if let Some(error) = &segment.error {
+            entrypoint_content += &format!("import Error{} from '{}';\n", j, error);
+        }
+        if let Some(loading) = &segment.loading {
+            entrypoint_content += &format!("import Loading{} from '{}';\n", j, loading);
+        }
+        if let Some(not_found) = &segment.not_found {
+            entrypoint_content += &format!("import NotFound{} from '{}';\n", j, not_found);
+        }
+        if is_server {
+            if let Some(head) = &segment.head {
+                entrypoint_content += &format!("import Head{} from '{}';\n", j, head);
+            }
+        }
+    }
+    entrypoint_content += "\n";
+
+    // Segments with an `error` and/or `not_found` module get a generated
+    // error boundary so a throw anywhere below is caught at that segment
+    // instead of unmounting the whole tree. A `not_found`-only segment still
+    // needs the boundary, since it's the only thing that catches the
+    // not-found sentinel and renders `NotFoundN`.
+    for (i, segment) in path_group.iter().enumerate() {
+        if segment.error.is_some() || segment.not_found.is_some() {
+            entrypoint_content +=
+                &build_error_boundary_component(i, segment.error.is_some(), segment.not_found.is_some());
+        }
     }
 
-    entrypoint_content += "\nconst Entrypoint = () => {\n";
+    entrypoint_content += "const Entrypoint = ({ onError } = {}) => {\n";
     entrypoint_content += "    mountLiveReload({SSR_RENDERING: process.env.SSR_RENDERING, NODE_ENV: process.env.NODE_ENV, LIVE_RELOAD_PORT: process.env.LIVE_RELOAD_PORT});\n";
     entrypoint_content += "    return (\n";
 
-    // Nest the layouts
-    for (i, _path) in path_group.iter().enumerate() {
-        entrypoint_content += &"        ".repeat(i + 1);
+    // Nest the layouts, wrapping each segment in its error boundary and/or
+    // suspense fallback when the segment declares one. Lazy layouts need an
+    // outer suspense boundary too, since any of them can now suspend while
+    // its chunk loads.
+    let mut depth = 0;
+    if lazy_layouts {
+        entrypoint_content += &"        ".repeat(depth + 1);
+        entrypoint_content += "<React.Suspense fallback={null}>\n";
+        depth += 1;
+    }
+    for (i, segment) in path_group.iter().enumerate() {
+        let has_boundary = segment.error.is_some() || segment.not_found.is_some();
+        if has_boundary {
+            entrypoint_content += &"        ".repeat(depth + 1);
+            entrypoint_content += &format!("<ErrorBoundary{} onError={{onError}}>\n", i);
+            depth += 1;
+        }
+        if segment.loading.is_some() {
+            entrypoint_content += &"        ".repeat(depth + 1);
+            entrypoint_content += &format!("<React.Suspense fallback={{<Loading{}/>}}>\n", i);
+            depth += 1;
+        }
+        entrypoint_content += &"        ".repeat(depth + 1);
         entrypoint_content += &format!("<Layout{}>\n", i);
+        depth += 1;
     }
 
-    // Close the nested layouts
-    for (i, _path) in path_group.iter().enumerate().rev() {
-        entrypoint_content += &"        ".repeat(i + 1);
+    // Close the nested layouts in reverse, unwinding suspense/error wrappers
+    // in the opposite order they were opened.
+    for (i, segment) in path_group.iter().enumerate().rev() {
+        depth -= 1;
+        entrypoint_content += &"        ".repeat(depth + 1);
         entrypoint_content += &format!("</Layout{}>\n", i);
+        if segment.loading.is_some() {
+            depth -= 1;
+            entrypoint_content += &"        ".repeat(depth + 1);
+            entrypoint_content += "</React.Suspense>\n";
+        }
+        if segment.error.is_some() || segment.not_found.is_some() {
+            depth -= 1;
+            entrypoint_content += &"        ".repeat(depth + 1);
+            entrypoint_content += &format!("</ErrorBoundary{}>\n", i);
+        }
+    }
+    if lazy_layouts {
+        depth -= 1;
+        entrypoint_content += &"        ".repeat(depth + 1);
+        entrypoint_content += "</React.Suspense>\n";
     }
 
     entrypoint_content += "    );\n";
@@ -42,21 +205,534 @@ pub fn build_entrypoint(
         entrypoint_content += "const container = document.getElementById('root');\n";
         entrypoint_content += "hydrateRoot(container, <Entrypoint />);\n";
     } else {
-        entrypoint_content += "// Dynamically import renderToString based on React version\n";
-        entrypoint_content += "async function getRenderToString() {\n";
-        entrypoint_content += "  const reactVersion = React.version;\n";
-        entrypoint_content += "  const majorVersion = parseInt(reactVersion.split('.')[0], 10);\n\n";
-        entrypoint_content += "  if (majorVersion >= 19) {\n";
-        entrypoint_content += "    return (await import('react-dom/server.edge')).renderToString;\n";
-        entrypoint_content += "  } else {\n";
-        entrypoint_content += "    return (await import('react-dom/server')).renderToString;\n";
-        entrypoint_content += "  }\n";
-        entrypoint_content += "}\n\n";
-        entrypoint_content += "export const Index = async () => {\n";
-        entrypoint_content += "  const renderToString = await getRenderToString();\n";
-        entrypoint_content += "  return renderToString(<Entrypoint />);\n";
-        entrypoint_content += "};\n";
+        let head_segments: Vec<Option<usize>> = path_group
+            .iter()
+            .enumerate()
+            .map(|(i, segment)| segment.head.as_ref().map(|_| i))
+            .collect();
+        if head_segments.iter().any(Option::is_some) {
+            entrypoint_content += &build_head_merge_helper();
+        }
+        match render_mode {
+            RenderMode::String => entrypoint_content += &build_server_string_block(&head_segments),
+            RenderMode::Stream => {
+                entrypoint_content += &build_server_stream_block(&head_segments, render_target)
+            }
+        }
     }
 
     entrypoint_content
 }
+
+/// Generates a per-segment error boundary class component, for segments
+/// that declare an `error` module, a `not_found` module, or both. It
+/// catches throws from the wrapped layout subtree and renders that
+/// segment's `error` module; when the segment also declares a `not_found`
+/// module, a thrown sentinel whose `digest` is `'NOT_FOUND'` is routed to that
+/// module instead of the generic error UI (or instead of re-throwing, when
+/// there's no `error` module to fall back to).
+fn build_error_boundary_component(index: usize, has_error: bool, has_not_found: bool) -> String {
+    let mut block = format!(
+        "class ErrorBoundary{index} extends React.Component {{\n",
+        index = index
+    );
+    block += "    constructor(props) {\n";
+    block += "        super(props);\n";
+    block += "        this.state = { error: null };\n";
+    block += "    }\n\n";
+    block += "    static getDerivedStateFromError(error) {\n";
+    block += "        return { error };\n";
+    block += "    }\n\n";
+    block += "    componentDidCatch(error, errorInfo) {\n";
+    block += "        if (this.props.onError) {\n";
+    block += "            this.props.onError(error, errorInfo);\n";
+    block += "        }\n";
+    block += "    }\n\n";
+    block += "    render() {\n";
+    block += "        if (this.state.error) {\n";
+    if has_not_found {
+        block += &format!(
+            "            if (this.state.error.digest === 'NOT_FOUND') {{\n                return <NotFound{index}/>;\n            }}\n",
+            index = index
+        );
+    }
+    if has_error {
+        block += &format!(
+            "            return <Error{index} error={{this.state.error}}/>;\n",
+            index = index
+        );
+    } else {
+        // No `error` module on this segment — we only caught the throw to
+        // check for the not-found sentinel above, so anything else
+        // propagates to the nearest ancestor boundary that can handle it.
+        block += "            throw this.state.error;\n";
+    }
+    block += "        }\n";
+    block += "        return this.props.children;\n";
+    block += "    }\n";
+    block += "}\n\n";
+    block
+}
+
+/// Generates the `mergeHeadTags` helper, which walks each segment's `head`
+/// output in route order so a child segment's `<title>`/`<meta name>`/
+/// `<link>` wins over its parents' when both declare the same tag key.
+fn build_head_merge_helper() -> String {
+    let mut block = String::new();
+    block += "function mergeHeadTags(tagsBySegment) {\n";
+    block += "  const merged = new Map();\n";
+    block += "  for (const tags of tagsBySegment) {\n";
+    block += "    for (const tag of tags) {\n";
+    block += "      const key = tag.type === 'title'\n";
+    block += "        ? 'title'\n";
+    block += "        : `${tag.type}:${tag.props.name || tag.props.property || tag.props.rel || tag.props.httpEquiv || JSON.stringify(tag.props)}`;\n";
+    block += "      merged.set(key, tag);\n";
+    block += "    }\n";
+    block += "  }\n";
+    block += "  return Array.from(merged.values());\n";
+    block += "}\n\n";
+    block
+}
+
+/// Builds the expression that computes the merged head for a single
+/// request. `head` modules are plain functions returning the segment's
+/// tags (in the spirit of Next.js's `generateMetadata`, not a component),
+/// so they're safe to call directly without a React render pass; calling
+/// them inside `Index` (rather than at module scope) keeps the result
+/// fresh per request instead of frozen at first import.
+fn build_head_compute_line(head_segments: &[Option<usize>]) -> String {
+    let mut line = String::from("  const head = mergeHeadTags([\n");
+    for index in head_segments.iter().flatten() {
+        line += &format!("    Head{}(),\n", index);
+    }
+    line += "  ]);\n";
+    line
+}
+
+/// Buffers the whole document via `renderToString` and returns it as one
+/// string. This is the original server rendering path, kept around for
+/// hosts that can't consume a stream.
+fn build_server_string_block(head_segments: &[Option<usize>]) -> String {
+    let has_head = head_segments.iter().any(Option::is_some);
+    let mut block = String::new();
+    block += "// Dynamically import renderToString based on React version\n";
+    block += "async function getRenderToString() {\n";
+    block += "  const reactVersion = React.version;\n";
+    block += "  const majorVersion = parseInt(reactVersion.split('.')[0], 10);\n\n";
+    block += "  if (majorVersion >= 19) {\n";
+    block += "    return (await import('react-dom/server.edge')).renderToString;\n";
+    block += "  } else {\n";
+    block += "    return (await import('react-dom/server')).renderToString;\n";
+    block += "  }\n";
+    block += "}\n\n";
+    block += "export const Index = async ({ onError } = {}) => {\n";
+    block += "  const renderToString = await getRenderToString();\n";
+    block += "  const html = renderToString(<Entrypoint onError={onError} />);\n";
+    if has_head {
+        block += &build_head_compute_line(head_segments);
+        block += "  return { html, head };\n";
+    } else {
+        block += "  return html;\n";
+    }
+    block += "};\n";
+    block
+}
+
+/// Streams the document instead of buffering the whole tree, via
+/// `renderToReadableStream` on the `Edge` target or `renderToPipeableStream`
+/// on the `Node` target (see [`RenderTarget`]). Render errors are forwarded
+/// through `onError` rather than swallowed, and the status is only flipped
+/// to 500 when the shell itself fails to render, not for errors recovered
+/// by a Suspense boundary's fallback. A failed shell still resolves `Index`,
+/// with a `null` stream (or `pipe`) and `status: 500`.
+fn build_server_stream_block(head_segments: &[Option<usize>], render_target: RenderTarget) -> String {
+    match render_target {
+        RenderTarget::Edge => build_server_stream_block_edge(head_segments),
+        RenderTarget::Node => build_server_stream_block_node(head_segments),
+    }
+}
+
+fn build_server_stream_block_edge(head_segments: &[Option<usize>]) -> String {
+    let has_head = head_segments.iter().any(Option::is_some);
+    let mut block = String::new();
+    block += "// Dynamically import the streaming renderer based on React version and target\n";
+    block += "async function getRenderToReadableStream() {\n";
+    block += "  const reactVersion = React.version;\n";
+    block += "  const majorVersion = parseInt(reactVersion.split('.')[0], 10);\n\n";
+    block += "  if (majorVersion >= 19) {\n";
+    block += "    return (await import('react-dom/server.edge')).renderToReadableStream;\n";
+    block += "  } else {\n";
+    block += "    return (await import('react-dom/server')).renderToReadableStream;\n";
+    block += "  }\n";
+    block += "}\n\n";
+    block += "export const Index = async ({ onError } = {}) => {\n";
+    block += "  const renderToReadableStream = await getRenderToReadableStream();\n";
+    block += "  let status = 200;\n";
+    block += "  let stream;\n";
+    block += "  try {\n";
+    block += "    // Resolves once the shell is ready, not once every Suspense boundary\n";
+    block += "    // has settled — that's what lets the host flush the shell early.\n";
+    block += "    stream = await renderToReadableStream(<Entrypoint onError={onError} />, { onError });\n";
+    block += "  } catch (error) {\n";
+    block += "    // Only a failure before the shell is ready lands here; errors inside\n";
+    block += "    // a Suspense boundary are handled by its fallback and reported via\n";
+    block += "    // onError above without affecting the response status.\n";
+    block += "    status = 500;\n";
+    block += "    if (onError) {\n";
+    block += "      onError(error);\n";
+    block += "    }\n";
+    block += "    stream = null;\n";
+    block += "  }\n";
+    if has_head {
+        block += &build_head_compute_line(head_segments);
+        block += "  return { stream, status, head };\n";
+    } else {
+        block += "  return { stream, status };\n";
+    }
+    block += "};\n";
+    block
+}
+
+fn build_server_stream_block_node(head_segments: &[Option<usize>]) -> String {
+    let has_head = head_segments.iter().any(Option::is_some);
+    let mut block = String::new();
+    block += "import { renderToPipeableStream } from 'react-dom/server';\n\n";
+    block += "export const Index = async ({ onError } = {}) => {\n";
+    block += "  let status = 200;\n";
+    block += "  // onShellReady always fires after this call returns (React never calls\n";
+    block += "  // it synchronously), so destructuring `pipe`/`abort` here and using them\n";
+    block += "  // inside the callback below is the pattern React's own docs use.\n";
+    block += "  const { pipe, abort } = await new Promise((resolve, reject) => {\n";
+    block += "    const { pipe, abort } = renderToPipeableStream(<Entrypoint onError={onError} />, {\n";
+    block += "      onShellReady() {\n";
+    block += "        resolve({ pipe, abort });\n";
+    block += "      },\n";
+    block += "      onShellError(error) {\n";
+    block += "        status = 500;\n";
+    block += "        if (onError) {\n";
+    block += "          onError(error);\n";
+    block += "        }\n";
+    block += "        resolve({ pipe: null, abort });\n";
+    block += "      },\n";
+    block += "      onError(error) {\n";
+    block += "        if (onError) {\n";
+    block += "          onError(error);\n";
+    block += "        }\n";
+    block += "      },\n";
+    block += "    });\n";
+    block += "  });\n";
+    if has_head {
+        block += &build_head_compute_line(head_segments);
+        block += "  return { pipe, abort, status, head };\n";
+    } else {
+        block += "  return { pipe, abort, status };\n";
+    }
+    block += "};\n";
+    block
+}
+
+/// Splits entrypoint generation into a server-only flight entry and a thin
+/// client entry, so layouts that never need interactivity don't ship to the
+/// browser. Server-only segments (`is_client_component: false`) are
+/// imported and rendered directly in the server entry; client segments are
+/// swapped for a client reference (`react.client.reference`) on the server.
+///
+/// The server entry's `Index` only produces the raw Flight byte stream
+/// (`renderToReadableStream(<Entrypoint/>, clientReferenceManifest)`) — it
+/// never renders HTML, so there's no per-segment DOM node for the client to
+/// attach to yet. Until that HTML pass (and matching island containers)
+/// exists, the client entry hydrates the client segments as their own
+/// nested tree onto a single `root` container, the same whole-document
+/// pattern [`build_entrypoint`]'s client branch uses, rather than mounting
+/// each one onto an island that nothing emits. Server-only segments are
+/// therefore absent from the client-rendered tree, not just un-hydrated —
+/// true per-island hydration needs the host to actually render the flight
+/// payload to HTML with `mountaineer-island-N` containers first.
+///
+/// Unlike [`build_entrypoint`], this only consults `layout` and
+/// `is_client_component` — `error`, `loading` and `head` on a segment are
+/// silently ignored here. Route groups that need error boundaries,
+/// loading fallbacks, or head merging should go through
+/// [`build_entrypoint`] instead until RSC mode grows support for them.
+///
+/// Returns `(server_entry, client_entry, client_reference_manifest)`.
+pub fn build_rsc_entrypoints(
+    path_group: &[SegmentModules],
+    live_reload_import: &str,
+) -> (String, String, Vec<ClientReference>) {
+    let manifest: Vec<ClientReference> = path_group
+        .iter()
+        .filter(|segment| segment.is_client_component)
+        .map(|segment| ClientReference {
+            segment_path: segment.layout.clone(),
+            chunk: segment.layout.clone(),
+            export_name: "default".to_string(),
+        })
+        .collect();
+
+    let mut server_entry = String::from("import React from 'react';\n");
+    server_entry += "import { renderToReadableStream } from 'react-server-dom-webpack/server.edge';\n\n";
+
+    for (i, segment) in path_group.iter().enumerate() {
+        if segment.is_client_component {
+            server_entry += &format!(
+                "const Layout{} = {{ $$typeof: Symbol.for('react.client.reference'), $$id: '{}#default' }};\n",
+                i, segment.layout
+            );
+        } else {
+            server_entry += &format!("import Layout{} from '{}';\n", i, segment.layout);
+        }
+    }
+
+    server_entry += "\nconst Entrypoint = () => {\n";
+    server_entry += "    return (\n";
+    for (i, _segment) in path_group.iter().enumerate() {
+        server_entry += &"        ".repeat(i + 1);
+        server_entry += &format!("<Layout{}>\n", i);
+    }
+    for (i, _segment) in path_group.iter().enumerate().rev() {
+        server_entry += &"        ".repeat(i + 1);
+        server_entry += &format!("</Layout{}>\n", i);
+    }
+    server_entry += "    );\n";
+    server_entry += "};\n\n";
+
+    server_entry += "const clientReferenceManifest = {\n";
+    for reference in &manifest {
+        server_entry += &format!(
+            "  '{}#default': {{ chunk: '{}', name: '{}' }},\n",
+            reference.segment_path, reference.chunk, reference.export_name
+        );
+    }
+    server_entry += "};\n\n";
+
+    server_entry += "export const Index = async () => {\n";
+    server_entry += "  const stream = renderToReadableStream(<Entrypoint />, clientReferenceManifest);\n";
+    server_entry += "  return { stream, status: 200 };\n";
+    server_entry += "};\n";
+
+    let client_segments: Vec<&SegmentModules> = path_group
+        .iter()
+        .filter(|segment| segment.is_client_component)
+        .collect();
+
+    let mut client_entry = String::from("import React from 'react';\n");
+    client_entry += &format!("import mountLiveReload from '{}';\n", live_reload_import);
+    client_entry += "import { hydrateRoot } from 'react-dom/client';\n";
+
+    for (i, segment) in client_segments.iter().enumerate() {
+        client_entry += &format!("import Layout{} from '{}';\n", i, segment.layout);
+    }
+
+    client_entry += "\nconst Entrypoint = () => {\n";
+    client_entry += "    mountLiveReload({SSR_RENDERING: process.env.SSR_RENDERING, NODE_ENV: process.env.NODE_ENV, LIVE_RELOAD_PORT: process.env.LIVE_RELOAD_PORT});\n";
+    client_entry += "    return (\n";
+    for (i, _segment) in client_segments.iter().enumerate() {
+        client_entry += &"        ".repeat(i + 1);
+        client_entry += &format!("<Layout{}>\n", i);
+    }
+    for (i, _segment) in client_segments.iter().enumerate().rev() {
+        client_entry += &"        ".repeat(i + 1);
+        client_entry += &format!("</Layout{}>\n", i);
+    }
+    client_entry += "    );\n";
+    client_entry += "};\n\n";
+
+    client_entry += "const container = document.getElementById('root');\n";
+    client_entry += "hydrateRoot(container, <Entrypoint />);\n";
+
+    (server_entry, client_entry, manifest)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn nests_layouts_in_order_and_closes_in_reverse() {
+        let path_group = vec![SegmentModules::new("./Layout0"), SegmentModules::new("./Layout1")];
+        let output = build_entrypoint(
+            &path_group,
+            false,
+            "./live-reload",
+            RenderMode::String,
+            RenderTarget::Edge,
+            LayoutImportStrategy::Eager,
+        );
+        let open0 = output.find("<Layout0>").unwrap();
+        let open1 = output.find("<Layout1>").unwrap();
+        let close1 = output.find("</Layout1>").unwrap();
+        let close0 = output.find("</Layout0>").unwrap();
+        assert!(open0 < open1 && open1 < close1 && close1 < close0);
+    }
+
+    #[test]
+    fn error_boundary_generated_for_not_found_only_segment() {
+        let mut segment = SegmentModules::new("./Layout0");
+        segment.not_found = Some("./NotFound0".to_string());
+        let output = build_entrypoint(
+            &[segment],
+            false,
+            "./live-reload",
+            RenderMode::String,
+            RenderTarget::Edge,
+            LayoutImportStrategy::Eager,
+        );
+        assert!(output.contains("class ErrorBoundary0"));
+        assert!(output.contains("<ErrorBoundary0 onError={onError}>"));
+        assert!(output.contains("import NotFound0 from './NotFound0';"));
+        // No `error` module on the segment, so unrecognized errors rethrow
+        // instead of referencing an Error0 component that was never imported.
+        assert!(!output.contains("import Error0"));
+        assert!(output.contains("throw this.state.error;"));
+    }
+
+    #[test]
+    fn on_error_is_threaded_from_entrypoint_to_error_boundary() {
+        let mut segment = SegmentModules::new("./Layout0");
+        segment.error = Some("./Error0".to_string());
+        let output = build_entrypoint(
+            &[segment],
+            true,
+            "./live-reload",
+            RenderMode::String,
+            RenderTarget::Edge,
+            LayoutImportStrategy::Eager,
+        );
+        assert!(output.contains("const Entrypoint = ({ onError } = {}) => {"));
+        assert!(output.contains("<ErrorBoundary0 onError={onError}>"));
+        assert!(output.contains("const html = renderToString(<Entrypoint onError={onError} />);"));
+    }
+
+    #[test]
+    fn loading_module_wraps_layout_in_suspense() {
+        let mut segment = SegmentModules::new("./Layout0");
+        segment.loading = Some("./Loading0".to_string());
+        let output = build_entrypoint(
+            &[segment],
+            false,
+            "./live-reload",
+            RenderMode::String,
+            RenderTarget::Edge,
+            LayoutImportStrategy::Eager,
+        );
+        let suspense_open = output.find("<React.Suspense fallback={<Loading0/>}>").unwrap();
+        let layout_open = output.find("<Layout0>").unwrap();
+        assert!(suspense_open < layout_open);
+    }
+
+    #[test]
+    fn lazy_strategy_emits_react_lazy_and_top_level_suspense_on_client_only() {
+        let path_group = vec![SegmentModules::new("./Layout0")];
+
+        let client_output = build_entrypoint(
+            &path_group,
+            false,
+            "./live-reload",
+            RenderMode::String,
+            RenderTarget::Edge,
+            LayoutImportStrategy::Lazy,
+        );
+        assert!(client_output.contains("React.lazy(() => import('./Layout0'))"));
+        assert!(client_output.contains("<React.Suspense fallback={null}>"));
+
+        let server_output = build_entrypoint(
+            &path_group,
+            true,
+            "./live-reload",
+            RenderMode::String,
+            RenderTarget::Edge,
+            LayoutImportStrategy::Lazy,
+        );
+        assert!(server_output.contains("import Layout0 from './Layout0';"));
+        assert!(!server_output.contains("React.lazy"));
+    }
+
+    #[test]
+    fn stream_mode_returns_shell_ready_without_waiting_on_all_ready() {
+        let path_group = vec![SegmentModules::new("./Layout0")];
+        let output = build_entrypoint(
+            &path_group,
+            true,
+            "./live-reload",
+            RenderMode::Stream,
+            RenderTarget::Edge,
+            LayoutImportStrategy::Eager,
+        );
+        assert!(!output.contains("stream.allReady"));
+        assert!(output.contains(
+            "stream = await renderToReadableStream(<Entrypoint onError={onError} />, { onError });"
+        ));
+    }
+
+    #[test]
+    fn stream_mode_on_node_target_uses_renderto_pipeable_stream() {
+        let path_group = vec![SegmentModules::new("./Layout0")];
+        let output = build_entrypoint(
+            &path_group,
+            true,
+            "./live-reload",
+            RenderMode::Stream,
+            RenderTarget::Node,
+            LayoutImportStrategy::Eager,
+        );
+        assert!(output.contains("import { renderToPipeableStream } from 'react-dom/server';"));
+        assert!(output.contains("renderToPipeableStream(<Entrypoint onError={onError} />, {"));
+        assert!(output.contains("onShellReady()"));
+        assert!(output.contains("return { pipe, abort, status };"));
+        assert!(!output.contains("renderToReadableStream"));
+    }
+
+    #[test]
+    fn head_only_changes_return_shape_when_a_segment_declares_one() {
+        let path_group_without_head = vec![SegmentModules::new("./Layout0")];
+        let without_head = build_entrypoint(
+            &path_group_without_head,
+            true,
+            "./live-reload",
+            RenderMode::String,
+            RenderTarget::Edge,
+            LayoutImportStrategy::Eager,
+        );
+        assert!(without_head.contains("return html;"));
+        assert!(!without_head.contains("mergeHeadTags"));
+
+        let mut segment_with_head = SegmentModules::new("./Layout0");
+        segment_with_head.head = Some("./Head0".to_string());
+        let with_head = build_entrypoint(
+            &[segment_with_head],
+            true,
+            "./live-reload",
+            RenderMode::String,
+            RenderTarget::Edge,
+            LayoutImportStrategy::Eager,
+        );
+        assert!(with_head.contains("function mergeHeadTags"));
+        assert!(with_head.contains("return { html, head };"));
+    }
+
+    #[test]
+    fn rsc_manifest_only_includes_client_components() {
+        let mut server_only = SegmentModules::new("./Layout0");
+        server_only.is_client_component = false;
+        let mut client_only = SegmentModules::new("./Layout1");
+        client_only.is_client_component = true;
+
+        let (server_entry, client_entry, manifest) =
+            build_rsc_entrypoints(&[server_only, client_only], "./live-reload");
+
+        assert_eq!(manifest.len(), 1);
+        assert_eq!(manifest[0].segment_path, "./Layout1");
+
+        assert!(server_entry.contains("import Layout0 from './Layout0';"));
+        assert!(server_entry.contains("react.client.reference"));
+        // The client entry only ever imports client segments, renumbered
+        // within its own tree — ./Layout0 (server-only) never appears.
+        assert!(!client_entry.contains("'./Layout0'"));
+        assert!(client_entry.contains("import Layout0 from './Layout1';"));
+        assert!(client_entry.contains("hydrateRoot(container, <Entrypoint />);"));
+        // No island id is referenced without anything in the pipeline ever
+        // emitting an element with that id.
+        assert!(!client_entry.contains("mountaineer-island"));
+    }
+}